@@ -1,11 +1,17 @@
-use actix_web::{web, App, HttpServer, Responder};
+use actix_web::{web, App, HttpServer, Responder, HttpResponse};
+use actix_web::http::StatusCode;
 use chrono::{NaiveDateTime, Utc};
 use dotenv::dotenv;
-use serde::Serialize;
+use futures_util::{stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::env;
-use std::time::SystemTime;
-use tokio_postgres::NoTls;
-use tokio_postgres::Config;
+use std::error::Error as StdError;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tokio::sync::broadcast;
+use tokio_postgres::{AsyncMessage, Config, NoTls};
 use deadpool_postgres::{Manager, ManagerConfig, Pool};
 
 
@@ -21,6 +27,387 @@ async fn health_check() -> impl Responder {
 }
 
 
+/// Errors that can surface while serving an API request.
+///
+/// Each variant carries the underlying cause so the handler can render a
+/// consistent JSON body and map the failure onto a meaningful HTTP status
+/// code instead of silently returning an empty result set.
+#[derive(Debug)]
+enum AppError {
+    /// Failed to acquire a client from the connection pool.
+    DbPoolError(String),
+    /// A query failed to execute against Postgres.
+    QueryError(String),
+    /// The requested resource produced no rows.
+    NotFound(String),
+    /// The request was malformed (bad query parameters).
+    BadRequest(String),
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::DbPoolError(cause) => write!(f, "database connection error: {}", cause),
+            AppError::QueryError(cause) => write!(f, "query execution error: {}", cause),
+            AppError::NotFound(what) => write!(f, "not found: {}", what),
+            AppError::BadRequest(why) => write!(f, "bad request: {}", why),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+/// JSON error body rendered for every [`AppError`].
+#[derive(Serialize)]
+struct ErrorBody {
+    message: String,
+    cause: String,
+}
+
+impl AppError {
+    /// Short, human-readable message describing the class of failure.
+    fn message(&self) -> &'static str {
+        match self {
+            AppError::DbPoolError(_) => "database unavailable",
+            AppError::QueryError(_) => "query failed",
+            AppError::NotFound(_) => "resource not found",
+            AppError::BadRequest(_) => "bad request",
+        }
+    }
+
+    /// The underlying cause, suitable for the `cause` field of the body.
+    fn cause(&self) -> String {
+        match self {
+            AppError::DbPoolError(cause)
+            | AppError::QueryError(cause)
+            | AppError::BadRequest(cause) => cause.clone(),
+            AppError::NotFound(what) => what.clone(),
+        }
+    }
+}
+
+impl actix_web::error::ResponseError for AppError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::DbPoolError(_) | AppError::QueryError(_) => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code()).json(ErrorBody {
+            message: self.message().to_string(),
+            cause: self.cause(),
+        })
+    }
+}
+
+impl From<deadpool_postgres::PoolError> for AppError {
+    fn from(e: deadpool_postgres::PoolError) -> Self {
+        AppError::DbPoolError(e.to_string())
+    }
+}
+
+impl From<tokio_postgres::Error> for AppError {
+    fn from(e: tokio_postgres::Error) -> Self {
+        AppError::QueryError(e.to_string())
+    }
+}
+
+
+/// Maximum number of retry attempts for transient transport failures.
+fn db_max_retries() -> u32 {
+    env::var("DB_MAX_RETRIES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(3)
+}
+
+/// Base backoff delay in milliseconds; each retry doubles it (capped).
+fn db_retry_base_ms() -> u64 {
+    env::var("DB_RETRY_BASE_MS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(50)
+}
+
+/// Returns `true` if the error is a connection/IO-level failure that may
+/// succeed on a fresh connection, rather than a genuine SQL error.
+///
+/// Server-side SQL errors carry a `SqlState` code and must fail
+/// immediately; closed connections and IO-level source errors are treated
+/// as transient transport failures worth retrying.
+fn is_transient(e: &tokio_postgres::Error) -> bool {
+    if e.code().is_some() {
+        return false;
+    }
+    e.is_closed()
+        || e.source()
+            .map_or(false, |s| s.downcast_ref::<std::io::Error>().is_some())
+}
+
+/// Returns `true` if a pool-acquire failure is a transport-level problem
+/// worth retrying — a dropped connection often surfaces here as a backend
+/// recycle/connect error or an acquisition timeout rather than at query
+/// time, so these get the same backoff as `client.query` failures.
+fn is_transient_pool(e: &deadpool_postgres::PoolError) -> bool {
+    use deadpool_postgres::PoolError;
+    match e {
+        PoolError::Backend(e) => is_transient(e),
+        PoolError::Timeout(_) => true,
+        _ => false,
+    }
+}
+
+/// Capped exponential backoff delay, in milliseconds, for `attempt`.
+fn backoff_delay(base_ms: u64, attempt: u32) -> u64 {
+    base_ms
+        .saturating_mul(1u64 << attempt.min(16))
+        .min(base_ms.saturating_mul(16))
+}
+
+/// Run `query` against a freshly acquired pooled client, retrying on
+/// transient transport failures with capped exponential backoff.
+///
+/// A dropped connection during the call re-acquires a new client from the
+/// pool and re-runs the query up to `DB_MAX_RETRIES` times, so a momentary
+/// network blip recovers transparently. Genuine SQL errors fail at once.
+async fn query_with_retry(
+    pool: &Pool,
+    query: &str,
+    params: &[&(dyn tokio_postgres::types::ToSql + Sync)],
+) -> Result<Vec<tokio_postgres::Row>, AppError> {
+    let max_retries = db_max_retries();
+    let base_ms = db_retry_base_ms();
+    let mut attempt: u32 = 0;
+
+    loop {
+        let client = match pool.get().await {
+            Ok(client) => client,
+            Err(e) => {
+                if attempt >= max_retries || !is_transient_pool(&e) {
+                    return Err(AppError::from(e));
+                }
+                let delay = backoff_delay(base_ms, attempt);
+                eprintln!(
+                    "Transient pool error (attempt {}/{}): {} — retrying in {}ms",
+                    attempt + 1,
+                    max_retries,
+                    e,
+                    delay
+                );
+                tokio::time::sleep(Duration::from_millis(delay)).await;
+                attempt += 1;
+                continue;
+            }
+        };
+        match client.query(query, params).await {
+            Ok(rows) => return Ok(rows),
+            Err(e) => {
+                if attempt >= max_retries || !is_transient(&e) {
+                    return Err(AppError::from(e));
+                }
+                let delay = backoff_delay(base_ms, attempt);
+                eprintln!(
+                    "Transient DB error (attempt {}/{}): {} — retrying in {}ms",
+                    attempt + 1,
+                    max_retries,
+                    e,
+                    delay
+                );
+                tokio::time::sleep(Duration::from_millis(delay)).await;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+
+/// Fan-out hub that shares a single DB listener across many HTTP
+/// subscribers, keyed by `signal_type`.
+///
+/// Each distinct `signal_type` gets its own [`broadcast`] channel, created
+/// lazily on first subscribe. The listener task routes every `NOTIFY`
+/// payload to the matching channel, so N streaming clients watching the
+/// same signal cost exactly one database connection.
+struct Notifier {
+    channels: Mutex<HashMap<String, broadcast::Sender<String>>>,
+}
+
+impl Notifier {
+    fn new() -> Arc<Self> {
+        Arc::new(Notifier {
+            channels: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Subscribe to live payloads for `signal_type`, creating the channel
+    /// if this is the first subscriber.
+    ///
+    /// The returned [`Subscription`] prunes the channel from the map once
+    /// its last receiver goes away, so a client hitting many distinct
+    /// `signal_type` paths can't grow the map without bound.
+    fn subscribe(self: &Arc<Self>, signal_type: &str) -> Subscription {
+        let rx = {
+            let mut channels = self.channels.lock().unwrap();
+            channels
+                .entry(signal_type.to_string())
+                .or_insert_with(|| broadcast::channel(128).0)
+                .subscribe()
+        };
+        Subscription {
+            notifier: self.clone(),
+            signal_type: signal_type.to_string(),
+            rx,
+        }
+    }
+
+    /// Drop the channel for `signal_type` once no receivers remain.
+    fn unsubscribe(&self, signal_type: &str) {
+        let mut channels = self.channels.lock().unwrap();
+        if let Some(tx) = channels.get(signal_type) {
+            // Our own receiver is dropping, so `<= 1` means nobody is left.
+            if tx.receiver_count() <= 1 {
+                channels.remove(signal_type);
+            }
+        }
+    }
+
+    /// Route a raw notification payload to the channel for its
+    /// `signal_type`; dropped if nobody is currently subscribed.
+    fn publish(&self, payload: &str) {
+        let signal_type = serde_json::from_str::<serde_json::Value>(payload)
+            .ok()
+            .and_then(|v| v.get("signal_type").and_then(|s| s.as_str()).map(str::to_string));
+        if let Some(signal_type) = signal_type {
+            let channels = self.channels.lock().unwrap();
+            if let Some(tx) = channels.get(&signal_type) {
+                let _ = tx.send(payload.to_string());
+            }
+        }
+    }
+}
+
+/// A live subscription to one `signal_type`, holding the broadcast receiver
+/// and, on drop (client disconnect), pruning its channel from the
+/// [`Notifier`] if it was the channel's last receiver.
+struct Subscription {
+    notifier: Arc<Notifier>,
+    signal_type: String,
+    rx: broadcast::Receiver<String>,
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        self.notifier.unsubscribe(&self.signal_type);
+    }
+}
+
+/// Background task that owns the sole `LISTEN machine_signals` connection
+/// and feeds every notification into the [`Notifier`].
+///
+/// A companion DB trigger is expected to emit
+/// `NOTIFY machine_signals, '<json payload>'` on insert (see
+/// `sql/notify_trigger.sql`). The task reconnects after a dropped
+/// connection so the stream survives transient network failures.
+async fn run_listener(cfg: Config, sslmode: String, notifier: Arc<Notifier>) {
+    loop {
+        if sslmode.eq_ignore_ascii_case("require") {
+            match cfg.connect(make_rustls_connector()).await {
+                Ok((client, connection)) => listen_once(client, connection, &notifier).await,
+                Err(e) => eprintln!("Listener failed to connect: {}", e),
+            }
+        } else {
+            match cfg.connect(NoTls).await {
+                Ok((client, connection)) => listen_once(client, connection, &notifier).await,
+                Err(e) => eprintln!("Listener failed to connect: {}", e),
+            }
+        }
+
+        eprintln!("Listener disconnected — reconnecting in 1s");
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+}
+
+/// Drive a single listener connection until it drops, routing every
+/// notification into the [`Notifier`]. Generic over the TLS stream so the
+/// same logic serves both the `NoTls` and rustls connectors.
+async fn listen_once<T>(
+    client: tokio_postgres::Client,
+    mut connection: tokio_postgres::Connection<tokio_postgres::Socket, T>,
+    notifier: &Arc<Notifier>,
+) where
+    T: tokio_postgres::tls::TlsStream + Unpin + Send + 'static,
+{
+    // Drain async messages off the connection onto a channel so the owning
+    // `client` can stay alive to keep the LISTEN open.
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let conn_task = tokio::spawn(async move {
+        let mut messages = stream::poll_fn(move |cx| connection.poll_message(cx));
+        while let Some(msg) = messages.next().await {
+            match msg {
+                Ok(AsyncMessage::Notification(note)) => {
+                    let _ = tx.send(note.payload().to_string());
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    eprintln!("Listener connection error: {}", e);
+                    break;
+                }
+            }
+        }
+    });
+
+    if let Err(e) = client.batch_execute("LISTEN machine_signals;").await {
+        eprintln!("Failed to issue LISTEN: {}", e);
+    }
+
+    while let Some(payload) = rx.recv().await {
+        notifier.publish(&payload);
+    }
+    conn_task.abort();
+}
+
+/// Server-Sent Events endpoint that pushes each new matching row as it is
+/// inserted, instead of forcing clients to poll `/signals`.
+async fn stream_signals(
+    notifier: web::Data<Arc<Notifier>>,
+    signal_type: web::Path<String>,
+) -> HttpResponse {
+    // Held for the lifetime of the stream; its drop prunes the channel
+    // once no other subscriber is left.
+    let mut sub = notifier.get_ref().subscribe(&signal_type.into_inner());
+
+    let body = async_stream::stream! {
+        // Greet the client so proxies see bytes immediately.
+        yield Ok::<web::Bytes, actix_web::Error>(web::Bytes::from_static(b": connected\n\n"));
+
+        let mut heartbeat = tokio::time::interval(Duration::from_secs(15));
+        loop {
+            tokio::select! {
+                _ = heartbeat.tick() => {
+                    // Comment line keeps idle proxies from timing out.
+                    yield Ok(web::Bytes::from_static(b": heartbeat\n\n"));
+                }
+                msg = sub.rx.recv() => match msg {
+                    Ok(payload) => yield Ok(web::Bytes::from(format!("data: {}\n\n", payload))),
+                    // Slow consumer fell behind; skip the gap and keep going.
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    // Listener gone: end the stream.
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+            }
+        }
+    };
+
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(body)
+}
+
+
 #[derive(Serialize)]
 struct Signal {
     id: i32,
@@ -32,27 +419,18 @@ struct Signal {
 
 async fn fetch_signals(pool: web::Data<Pool>,
                         signal_type: web::Path<String>
-) -> impl Responder {
-
-    let client = match pool.get().await {
-        Ok(conn) => conn,
-        Err(e) => {
-            eprintln!("Failed to get database connection: {}", e);
-            return web::Json(Vec::<Signal>::new());
-        }
-    };
+) -> Result<web::Json<Vec<Signal>>, AppError> {
 
     let query = "SELECT id, signal_type, value, timestamp FROM machine_signals \
                  WHERE signal_type = $1 \
                  ORDER BY timestamp DESC LIMIT 10;";
 
-    let rows = match client.query(query, &[&signal_type.as_str()]).await {
-        Ok(rows) => rows,
-        Err(e) => {
-            eprintln!("Query execution error: {}", e);
-            return web::Json(Vec::<Signal>::new());
-        }
-    };
+    let signal_type = signal_type.into_inner();
+    let rows = query_with_retry(&pool, query, &[&signal_type]).await?;
+
+    if rows.is_empty() {
+        return Err(AppError::NotFound(format!("signal_type `{}`", signal_type)));
+    }
 
     let signals: Vec<Signal> = rows
         .into_iter()
@@ -69,23 +447,312 @@ async fn fetch_signals(pool: web::Data<Pool>,
         .collect();
     println!("Fetched data");
 
-    web::Json(signals)
+    Ok(web::Json(signals))
 }
 
-#[actix_web::main]
-async fn main() -> std::io::Result<()> {
-    dotenv().ok(); // Load environment variables from .env file
+/// Build the Postgres connection config, registering one host (and port)
+/// per comma-separated entry in `DB_HOST`/`DB_PORT`.
+///
+/// `tokio_postgres` tries each host/port candidate in order until one
+/// connects, giving basic primary/replica failover. A single port applies
+/// to every host; when several ports are given their count must match the
+/// host count, mirroring tokio-postgres' own validation.
+fn build_db_config() -> Config {
+    let hosts = env::var("DB_HOST").unwrap_or_else(|_| "localhost".to_string());
+    let hosts: Vec<&str> = hosts.split(',').map(|h| h.trim()).collect();
+
+    let ports = env::var("DB_PORT").unwrap_or_else(|_| "5432".to_string());
+    let ports: Vec<u16> = ports
+        .split(',')
+        .map(|p| {
+            p.trim()
+                .parse()
+                .unwrap_or_else(|_| panic!("invalid DB_PORT entry: {}", p))
+        })
+        .collect();
+
+    if ports.len() != 1 && ports.len() != hosts.len() {
+        panic!(
+            "invalid number of ports ({}) for number of hosts ({})",
+            ports.len(),
+            hosts.len()
+        );
+    }
 
     let mut cfg = Config::new();
-    cfg.host(&env::var("DB_HOST").unwrap_or_else(|_| "localhost".to_string()));
+    for (i, host) in hosts.iter().enumerate() {
+        cfg.host(host);
+        let port = if ports.len() == 1 { ports[0] } else { ports[i] };
+        cfg.port(port);
+    }
     cfg.user(&env::var("DB_USER").unwrap_or_else(|_| "postgres".to_string()));
     cfg.password(&env::var("DB_PASSWORD").unwrap_or_else(|_| "".to_string()));
     cfg.dbname(&env::var("DB_NAME").unwrap_or_else(|_| "machine_data".to_string()));
+    cfg
+}
+
+/// Query-string parameters for the filtered/aggregated signals endpoint.
+#[derive(Deserialize)]
+struct SignalQuery {
+    /// Inclusive lower bound (RFC3339) on `timestamp`.
+    from: Option<String>,
+    /// Inclusive upper bound (RFC3339) on `timestamp`.
+    to: Option<String>,
+    /// Maximum number of rows to return.
+    limit: Option<i64>,
+    /// Aggregation mode: `raw`, `avg`, `min`, `max` or `count`.
+    agg: Option<String>,
+    /// `date_trunc` field (`minute`, `hour`, `day`, ...) for bucketing.
+    bucket: Option<String>,
+}
+
+/// The aggregation applied to the `value` column.
+enum Agg {
+    Raw,
+    Avg,
+    Min,
+    Max,
+    Count,
+}
+
+impl Agg {
+    fn parse(s: &str) -> Result<Agg, AppError> {
+        match s {
+            "raw" => Ok(Agg::Raw),
+            "avg" => Ok(Agg::Avg),
+            "min" => Ok(Agg::Min),
+            "max" => Ok(Agg::Max),
+            "count" => Ok(Agg::Count),
+            other => Err(AppError::BadRequest(format!("unknown agg `{}`", other))),
+        }
+    }
+
+    /// The SQL aggregate expression over `value`, or `None` for `raw`.
+    fn expr(&self) -> Option<&'static str> {
+        match self {
+            Agg::Raw => None,
+            Agg::Avg => Some("avg(value)"),
+            Agg::Min => Some("min(value)"),
+            Agg::Max => Some("max(value)"),
+            Agg::Count => Some("count(value)"),
+        }
+    }
+}
+
+/// Bucketing fields accepted for `date_trunc`, validated against an
+/// allow-list so the value can be bound as a parameter without risk.
+const ALLOWED_BUCKETS: &[&str] = &[
+    "second", "minute", "hour", "day", "week", "month", "quarter", "year",
+];
+
+/// A tabular response carrying explicit column names alongside typed rows,
+/// so callers can render raw, windowed or downsampled views uniformly.
+#[derive(Serialize)]
+struct QueryResponse {
+    column_names: Vec<String>,
+    rows: Vec<Vec<serde_json::Value>>,
+}
+
+fn system_time_to_rfc3339(ts: SystemTime) -> String {
+    chrono::DateTime::<Utc>::from(ts).to_rfc3339()
+}
+
+/// Filtered and optionally aggregated view over a single signal type.
+///
+/// Builds its SQL with bound parameters only — user input never reaches
+/// the query string directly. `agg` plus an optional `bucket` translate
+/// into a `date_trunc`/`GROUP BY` aggregation for time-series windows.
+async fn query_signals(
+    pool: web::Data<Pool>,
+    signal_type: web::Path<String>,
+    params: web::Query<SignalQuery>,
+) -> Result<web::Json<QueryResponse>, AppError> {
+    let agg = Agg::parse(params.agg.as_deref().unwrap_or("raw"))?;
+
+    // Parse the optional time bounds up front so bad input fails fast.
+    let from = parse_bound(params.from.as_deref(), "from")?;
+    let to = parse_bound(params.to.as_deref(), "to")?;
+
+    if let Some(bucket) = params.bucket.as_deref() {
+        if !ALLOWED_BUCKETS.contains(&bucket) {
+            return Err(AppError::BadRequest(format!("unknown bucket `{}`", bucket)));
+        }
+        // A bucket only makes sense with an aggregation; don't silently drop it.
+        if matches!(agg, Agg::Raw) {
+            return Err(AppError::BadRequest(
+                "bucket requires an aggregation (agg=avg|min|max|count)".to_string(),
+            ));
+        }
+    }
+
+    if let Some(limit) = params.limit {
+        if limit < 0 {
+            return Err(AppError::BadRequest(format!(
+                "limit must not be negative (got {})",
+                limit
+            )));
+        }
+    }
+
+    let signal_type = signal_type.into_inner();
+
+    // Owned parameter values, referenced by the slice handed to the query.
+    let mut owned: Vec<Box<dyn tokio_postgres::types::ToSql + Sync>> =
+        vec![Box::new(signal_type)];
+    let mut sql = String::new();
+    let mut column_names: Vec<String> = Vec::new();
+
+    let bucketed = agg.expr().is_some() && params.bucket.is_some();
+    match (agg.expr(), params.bucket.as_deref()) {
+        (None, _) => {
+            sql.push_str("SELECT id, signal_type, value, timestamp FROM machine_signals");
+            column_names = ["id", "signal_type", "value", "timestamp"]
+                .iter()
+                .map(|c| c.to_string())
+                .collect();
+        }
+        (Some(expr), Some(_)) => {
+            // One aggregate value per time bucket.
+            owned.push(Box::new(params.bucket.clone().unwrap()));
+            sql.push_str(&format!(
+                "SELECT date_trunc(${}, timestamp) AS bucket, {} AS value FROM machine_signals",
+                owned.len(),
+                expr
+            ));
+            column_names = vec!["bucket".to_string(), "value".to_string()];
+        }
+        (Some(expr), None) => {
+            // A single aggregate over the whole window.
+            sql.push_str(&format!("SELECT {} AS value FROM machine_signals", expr));
+            column_names = vec!["value".to_string()];
+        }
+    }
+
+    sql.push_str(" WHERE signal_type = $1");
+    if let Some(from) = from {
+        owned.push(Box::new(from));
+        sql.push_str(&format!(" AND timestamp >= ${}", owned.len()));
+    }
+    if let Some(to) = to {
+        owned.push(Box::new(to));
+        sql.push_str(&format!(" AND timestamp <= ${}", owned.len()));
+    }
+
+    if bucketed {
+        sql.push_str(" GROUP BY bucket ORDER BY bucket DESC");
+    } else if agg.expr().is_none() {
+        sql.push_str(" ORDER BY timestamp DESC");
+    }
+
+    // `count`/aggregate-without-bucket always yields a single row.
+    if agg.expr().is_none() || bucketed {
+        let limit = params.limit.unwrap_or(100);
+        owned.push(Box::new(limit));
+        sql.push_str(&format!(" LIMIT ${}", owned.len()));
+    }
+
+    let slice: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> =
+        owned.iter().map(|b| b.as_ref()).collect();
+    let rows = query_with_retry(&pool, &sql, &slice).await?;
+
+    let is_count = matches!(agg, Agg::Count);
+    let out_rows: Vec<Vec<serde_json::Value>> = rows
+        .into_iter()
+        .map(|row| match (agg.expr(), bucketed) {
+            (None, _) => {
+                let ts: SystemTime = row.get(3);
+                vec![
+                    serde_json::json!(row.get::<_, i32>(0)),
+                    serde_json::json!(row.get::<_, String>(1)),
+                    serde_json::json!(row.get::<_, f64>(2)),
+                    serde_json::json!(system_time_to_rfc3339(ts)),
+                ]
+            }
+            (Some(_), true) => {
+                let ts: SystemTime = row.get(0);
+                let value = agg_value(&row, 1, is_count);
+                vec![serde_json::json!(system_time_to_rfc3339(ts)), value]
+            }
+            (Some(_), false) => vec![agg_value(&row, 0, is_count)],
+        })
+        .collect();
 
-    // Create the connection pool
+    Ok(web::Json(QueryResponse {
+        column_names,
+        rows: out_rows,
+    }))
+}
+
+/// Read an aggregate column as the right numeric JSON type.
+fn agg_value(row: &tokio_postgres::Row, idx: usize, is_count: bool) -> serde_json::Value {
+    if is_count {
+        serde_json::json!(row.get::<_, i64>(idx))
+    } else {
+        serde_json::json!(row.get::<_, Option<f64>>(idx))
+    }
+}
+
+/// Parse an optional RFC3339 bound into a `SystemTime` for binding.
+fn parse_bound(value: Option<&str>, name: &str) -> Result<Option<SystemTime>, AppError> {
+    match value {
+        None => Ok(None),
+        Some(s) => chrono::DateTime::parse_from_rfc3339(s)
+            .map(|dt| Some(SystemTime::from(dt.with_timezone(&Utc))))
+            .map_err(|e| AppError::BadRequest(format!("invalid `{}` timestamp: {}", name, e))),
+    }
+}
+
+/// Runtime knobs loaded once from the environment at startup.
+struct AppConfig {
+    /// `require` enables TLS; anything else (default `disable`) uses `NoTls`.
+    sslmode: String,
+    /// Pool capacity; defaults to `num_cpus::get() * 4` when unset.
+    pool_size: usize,
+}
+
+impl AppConfig {
+    fn load() -> Self {
+        let sslmode = env::var("DB_SSLMODE").unwrap_or_else(|_| "disable".to_string());
+        let pool_size = env::var("DB_POOL_SIZE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or_else(|| num_cpus::get() * 4);
+        AppConfig { sslmode, pool_size }
+    }
+}
+
+/// Build a rustls-backed TLS connector trusting the platform root store.
+fn make_rustls_connector() -> tokio_postgres_rustls::MakeRustlsConnect {
+    let mut roots = rustls::RootCertStore::empty();
+    for cert in rustls_native_certs::load_native_certs().expect("failed to load native certs") {
+        let _ = roots.add(&rustls::Certificate(cert.0));
+    }
+    let config = rustls::ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    tokio_postgres_rustls::MakeRustlsConnect::new(config)
+}
+
+#[actix_web::main]
+async fn main() -> std::io::Result<()> {
+    dotenv().ok(); // Load environment variables from .env file
+
+    let app_cfg = AppConfig::load();
+    let cfg = build_db_config();
+
+    // Create the connection pool, wiring in TLS when requested.
     let mgr_config = ManagerConfig { recycling_method: deadpool_postgres::RecyclingMethod::Fast };
-    let mgr = Manager::from_config(cfg, NoTls, mgr_config);
-    let pool = Pool::builder(mgr).max_size(16).build().unwrap();
+    let mgr = if app_cfg.sslmode.eq_ignore_ascii_case("require") {
+        Manager::from_config(cfg.clone(), make_rustls_connector(), mgr_config)
+    } else {
+        Manager::from_config(cfg.clone(), NoTls, mgr_config)
+    };
+    let pool = Pool::builder(mgr).max_size(app_cfg.pool_size).build().unwrap();
+
+    // Fan-out hub for live notifications, fed by a single DB listener task.
+    let notifier = Notifier::new();
+    tokio::spawn(run_listener(cfg, app_cfg.sslmode.clone(), notifier.clone()));
 
 
     let host = env::var("SERVER_HOST").unwrap_or_else(|_| "127.0.0.1".to_string());
@@ -99,8 +766,11 @@ async fn main() -> std::io::Result<()> {
     let server = HttpServer::new(move || {
         App::new()
             .app_data(web::Data::new(pool.clone()))
+            .app_data(web::Data::new(notifier.clone()))
             .route("/health",web::get().to(health_check))
             .route("/signals/{signal_type}",web::get().to(fetch_signals))
+            .route("/signals/{signal_type}/stream",web::get().to(stream_signals))
+            .route("/signals/{signal_type}/query",web::get().to(query_signals))
 
     })
     .bind((host.as_str(), port))?